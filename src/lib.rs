@@ -1,8 +1,15 @@
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
 use image::imageops::FilterType;
 use lopdf::{Document, Object};
 use pixo::jpeg::JpegOptions;
 use pixo::png::{PngOptions, QuantizationMode};
 use pixo::{ColorType, jpeg, png};
+use std::io::{Cursor, Read, Write};
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::encoder::{TiffEncoder, colortype};
+use tiff::encoder::compression::{Deflate, Lzw, Packbits, Uncompressed};
 use wasm_bindgen::prelude::*;
 
 #[inline]
@@ -48,6 +55,9 @@ fn choose_out_mode(mime: &str, ext: &str, out_mode_sel: &str) -> String {
     if mime.contains("png") || ext == "png" {
         return "png".to_string();
     }
+    if mime.contains("tiff") || ext == "tiff" || ext == "tif" {
+        return "tiff".to_string();
+    }
     "jpeg".to_string()
 }
 
@@ -58,6 +68,13 @@ fn is_pdf(mime: &str, ext: &str, bytes: &[u8]) -> bool {
     bytes.len() >= 4 && &bytes[0..4] == b"%PDF"
 }
 
+fn is_tiff(mime: &str, ext: &str, bytes: &[u8]) -> bool {
+    if mime.contains("tiff") || ext == "tiff" || ext == "tif" {
+        return true;
+    }
+    bytes.len() >= 4 && (&bytes[0..4] == b"II\x2a\x00" || &bytes[0..4] == b"MM\x00\x2a")
+}
+
 fn input_kind(mime: &str, ext: &str) -> &'static str {
     if mime.contains("pdf") || ext == "pdf" {
         return "pdf";
@@ -65,6 +82,9 @@ fn input_kind(mime: &str, ext: &str) -> &'static str {
     if mime.contains("png") || ext == "png" {
         return "png";
     }
+    if mime.contains("tiff") || ext == "tiff" || ext == "tif" {
+        return "tiff";
+    }
     if mime.contains("jpg") || mime.contains("jpeg") || ext == "jpg" || ext == "jpeg" {
         return "jpeg";
     }
@@ -171,12 +191,22 @@ pub fn encode_png_rgba(
     max_colors: u16,
     dithering: bool,
     force_quant: bool,
+    effort: u8,
 ) -> Vec<u8> {
     let px = (width as usize).saturating_mul(height as usize);
     if rgba.len() < px.saturating_mul(4) || px == 0 {
         return Vec::new();
     }
 
+    // In lossless mode run the oxipng-style reduction + filter trials, which
+    // emits the reduced representation directly instead of a flat RGBA stream.
+    if lossless {
+        let (optimized, _, _) = optimize_png_lossless(&rgba, width, height, effort);
+        if !optimized.is_empty() {
+            return optimized;
+        }
+    }
+
     let preset = clamp_u8(preset, 0, 2);
     let mut opts = PngOptions::from_preset_with_lossless(width, height, preset, lossless);
     opts.color_type = ColorType::Rgba;
@@ -194,6 +224,406 @@ pub fn encode_png_rgba(
     png::encode(&rgba, &opts).unwrap_or_default()
 }
 
+/// Lossless PNG reduction + filter/deflate trial subsystem, modeled on oxipng.
+///
+/// The source is always handed to us as 8-bit RGBA. Before deflating we scan the
+/// whole buffer to find the minimal viable representation (drop alpha, collapse to
+/// grayscale, or build an indexed palette with reduced bit depth), then try a set
+/// of scanline filters and keep whichever produces the smallest compressed stream.
+/// The `effort` knob bounds how many filter strategies are attempted.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc: u32 = 0xffff_ffff;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    crc ^ 0xffff_ffff
+}
+
+fn push_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let mut crc_input = Vec::with_capacity(4 + data.len());
+    crc_input.extend_from_slice(kind);
+    crc_input.extend_from_slice(data);
+    out.extend_from_slice(&crc32(&crc_input).to_be_bytes());
+}
+
+#[inline]
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i32 + b as i32 - c as i32;
+    let pa = (p - a as i32).abs();
+    let pb = (p - b as i32).abs();
+    let pc = (p - c as i32).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Filter one scanline with a fixed filter type into `out`, returning the sum of
+/// absolute signed filtered bytes (the oxipng "minimum sum of absolute differences"
+/// score used to pick a per-line filter).
+fn filter_line(filter: u8, cur: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) -> u64 {
+    out.push(filter);
+    let mut score: u64 = 0;
+    for i in 0..cur.len() {
+        let a = if i >= bpp { cur[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let v = match filter {
+            1 => cur[i].wrapping_sub(a),
+            2 => cur[i].wrapping_sub(b),
+            3 => cur[i].wrapping_sub(((a as u16 + b as u16) / 2) as u8),
+            4 => cur[i].wrapping_sub(paeth(a, b, c)),
+            _ => cur[i],
+        };
+        out.push(v);
+        score += (v as i8).unsigned_abs() as u64;
+    }
+    score
+}
+
+/// Build a filtered byte stream for the whole image. `strategy` is either a fixed
+/// filter (`Some`) applied to every line, or adaptive (`None`) which picks the
+/// minimum-sum filter independently per line.
+fn filter_image(rows: &[Vec<u8>], bpp: usize, strategy: Option<u8>) -> Vec<u8> {
+    let row_len = rows.first().map(|r| r.len()).unwrap_or(0);
+    let zero = vec![0u8; row_len];
+    let mut out = Vec::with_capacity((row_len + 1) * rows.len());
+    let mut scratch = Vec::with_capacity(row_len + 1);
+    for (i, row) in rows.iter().enumerate() {
+        let prev: &[u8] = if i == 0 { &zero } else { &rows[i - 1] };
+        match strategy {
+            Some(f) => {
+                filter_line(f, row, prev, bpp, &mut out);
+            }
+            None => {
+                let mut best: Option<(u64, Vec<u8>)> = None;
+                for f in 0u8..=4 {
+                    scratch.clear();
+                    let score = filter_line(f, row, prev, bpp, &mut scratch);
+                    if best.as_ref().map(|(s, _)| score < *s).unwrap_or(true) {
+                        best = Some((score, scratch.clone()));
+                    }
+                }
+                out.extend_from_slice(&best.unwrap().1);
+            }
+        }
+    }
+    out
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    if encoder.write_all(data).is_err() {
+        return Vec::new();
+    }
+    encoder.finish().unwrap_or_default()
+}
+
+/// The minimal viable PNG representation chosen for a buffer.
+struct PngPlan {
+    color_type: u8,
+    bit_depth: u8,
+    rows: Vec<Vec<u8>>,
+    palette: Vec<[u8; 3]>,
+    trns: Vec<u8>,
+}
+
+/// Smallest grayscale bit depth that can represent every value exactly, or `8`
+/// when the values do not fall on an evenly spaced 1/2/4-bit ladder.
+fn gray_bit_depth(values: &[bool; 256]) -> u8 {
+    for &depth in &[1u8, 2, 4] {
+        let max = (1u16 << depth) - 1;
+        let step = 255 / max;
+        let mut ok = true;
+        for (v, &present) in values.iter().enumerate() {
+            if present && (v as u16) % step != 0 {
+                ok = false;
+                break;
+            }
+        }
+        if ok {
+            return depth;
+        }
+    }
+    8
+}
+
+fn pack_samples(samples: &[u8], bit_depth: u8, width: usize) -> Vec<Vec<u8>> {
+    let height = samples.len() / width.max(1);
+    let mut rows = Vec::with_capacity(height);
+    let per_byte = 8 / bit_depth as usize;
+    for y in 0..height {
+        let mut row = Vec::with_capacity(width.div_ceil(per_byte));
+        let mut acc = 0u8;
+        let mut filled = 0usize;
+        for x in 0..width {
+            let s = samples[y * width + x] & ((1 << bit_depth) - 1);
+            acc = (acc << bit_depth) | s;
+            filled += 1;
+            if filled == per_byte {
+                row.push(acc);
+                acc = 0;
+                filled = 0;
+            }
+        }
+        if filled > 0 {
+            acc <<= (per_byte - filled) * bit_depth as usize;
+            row.push(acc);
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+fn plan_png(rgba: &[u8], width: usize, height: usize) -> PngPlan {
+    let px = width * height;
+    let mut opaque = true;
+    let mut gray = true;
+    let mut gray_seen = [false; 256];
+    // Distinct colors (RGBA), capped at 257 so we can bail out of palette mode fast.
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut too_many = false;
+    for i in 0..px {
+        let r = rgba[i * 4];
+        let g = rgba[i * 4 + 1];
+        let b = rgba[i * 4 + 2];
+        let a = rgba[i * 4 + 3];
+        if a != 255 {
+            opaque = false;
+        }
+        if r != g || g != b {
+            gray = false;
+        } else {
+            gray_seen[r as usize] = true;
+        }
+        if !too_many && !palette.iter().any(|c| *c == [r, g, b, a]) {
+            if palette.len() == 256 {
+                too_many = true;
+            } else {
+                palette.push([r, g, b, a]);
+            }
+        }
+    }
+
+    // Grayscale beats a palette (no PLTE overhead) whenever the image is gray.
+    if gray {
+        let depth = gray_bit_depth(&gray_seen);
+        let max = (1u16 << depth.min(8)) - 1;
+        let step = if depth == 8 { 1 } else { 255 / max };
+        if opaque {
+            let samples: Vec<u8> = (0..px).map(|i| (rgba[i * 4] as u16 / step) as u8).collect();
+            let rows = if depth == 8 {
+                (0..height)
+                    .map(|y| samples[y * width..(y + 1) * width].to_vec())
+                    .collect()
+            } else {
+                pack_samples(&samples, depth, width)
+            };
+            return PngPlan {
+                color_type: 0,
+                bit_depth: depth,
+                rows,
+                palette: Vec::new(),
+                trns: Vec::new(),
+            };
+        }
+        // Gray + alpha keeps 8-bit samples (sub-8 depths are not defined for this type).
+        let mut rows = Vec::with_capacity(height);
+        for y in 0..height {
+            let mut row = Vec::with_capacity(width * 2);
+            for x in 0..width {
+                let i = y * width + x;
+                row.push(rgba[i * 4]);
+                row.push(rgba[i * 4 + 3]);
+            }
+            rows.push(row);
+        }
+        return PngPlan {
+            color_type: 4,
+            bit_depth: 8,
+            rows,
+            palette: Vec::new(),
+            trns: Vec::new(),
+        };
+    }
+
+    if !too_many {
+        let bit_depth = match palette.len() {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=16 => 4,
+            _ => 8,
+        };
+        let index_of = |c: &[u8; 4]| palette.iter().position(|p| p == c).unwrap() as u8;
+        let samples: Vec<u8> = (0..px)
+            .map(|i| {
+                index_of(&[
+                    rgba[i * 4],
+                    rgba[i * 4 + 1],
+                    rgba[i * 4 + 2],
+                    rgba[i * 4 + 3],
+                ])
+            })
+            .collect();
+        let rows = if bit_depth == 8 {
+            (0..height)
+                .map(|y| samples[y * width..(y + 1) * width].to_vec())
+                .collect()
+        } else {
+            pack_samples(&samples, bit_depth, width)
+        };
+        let rgb_palette: Vec<[u8; 3]> = palette.iter().map(|c| [c[0], c[1], c[2]]).collect();
+        // tRNS holds the palette alphas, minus the trailing run of opaque entries.
+        let mut trns: Vec<u8> = palette.iter().map(|c| c[3]).collect();
+        while trns.last() == Some(&255) {
+            trns.pop();
+        }
+        return PngPlan {
+            color_type: 3,
+            bit_depth,
+            rows,
+            palette: rgb_palette,
+            trns,
+        };
+    }
+
+    // Full-color fall-through: drop alpha only when every pixel is opaque.
+    if opaque {
+        let mut rows = Vec::with_capacity(height);
+        for y in 0..height {
+            let mut row = Vec::with_capacity(width * 3);
+            for x in 0..width {
+                let i = y * width + x;
+                row.push(rgba[i * 4]);
+                row.push(rgba[i * 4 + 1]);
+                row.push(rgba[i * 4 + 2]);
+            }
+            rows.push(row);
+        }
+        PngPlan {
+            color_type: 2,
+            bit_depth: 8,
+            rows,
+            palette: Vec::new(),
+            trns: Vec::new(),
+        }
+    } else {
+        let rows = (0..height)
+            .map(|y| rgba[y * width * 4..(y + 1) * width * 4].to_vec())
+            .collect();
+        PngPlan {
+            color_type: 6,
+            bit_depth: 8,
+            rows,
+            palette: Vec::new(),
+            trns: Vec::new(),
+        }
+    }
+}
+
+/// Pick the set of filter strategies to attempt for the given effort level.
+/// 0 = filter None only; 1 = per-line adaptive heuristic; 2+ = all five fixed
+/// filters plus the adaptive heuristic, keeping the smallest deflated candidate.
+fn filter_strategies(effort: u8) -> Vec<Option<u8>> {
+    match effort {
+        0 => vec![Some(0)],
+        1 => vec![None],
+        _ => vec![Some(0), Some(1), Some(2), Some(3), Some(4), None],
+    }
+}
+
+fn pixo_color_type(color_type: u8) -> ColorType {
+    match color_type {
+        0 => ColorType::Grayscale,
+        2 => ColorType::Rgb,
+        3 => ColorType::Indexed,
+        4 => ColorType::GrayscaleAlpha,
+        _ => ColorType::Rgba,
+    }
+}
+
+/// Produce an oxipng-competitive lossless PNG from an 8-bit RGBA buffer.
+///
+/// Returns the encoded bytes alongside the reduced color type and bit depth that
+/// were selected, so callers can inspect or re-use the chosen representation.
+fn optimize_png_lossless(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    effort: u8,
+) -> (Vec<u8>, ColorType, u8) {
+    let (w, h) = (width as usize, height as usize);
+    let px = w.saturating_mul(h);
+    if px == 0 || rgba.len() < px * 4 {
+        return (Vec::new(), ColorType::Rgba, 8);
+    }
+
+    let plan = plan_png(rgba, w, h);
+    let bpp = match plan.color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        _ => 4,
+    };
+    // Sub-byte depths are filtered with bpp = 1 per the PNG spec.
+    let filter_bpp = if plan.bit_depth < 8 { 1 } else { bpp };
+
+    let mut idat: Option<Vec<u8>> = None;
+    for strategy in filter_strategies(effort) {
+        let filtered = filter_image(&plan.rows, filter_bpp, strategy);
+        let candidate = deflate(&filtered);
+        if candidate.is_empty() {
+            continue;
+        }
+        if idat.as_ref().map(|b| candidate.len() < b.len()).unwrap_or(true) {
+            idat = Some(candidate);
+        }
+    }
+    let idat = match idat {
+        Some(idat) => idat,
+        None => return (Vec::new(), pixo_color_type(plan.color_type), plan.bit_depth),
+    };
+
+    let mut out = Vec::with_capacity(idat.len() + 64);
+    out.extend_from_slice(&PNG_SIGNATURE);
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.push(plan.bit_depth);
+    ihdr.push(plan.color_type);
+    ihdr.extend_from_slice(&[0, 0, 0]); // deflate, adaptive filtering, no interlace
+    push_chunk(&mut out, b"IHDR", &ihdr);
+
+    if plan.color_type == 3 {
+        let mut plte = Vec::with_capacity(plan.palette.len() * 3);
+        for c in &plan.palette {
+            plte.extend_from_slice(c);
+        }
+        push_chunk(&mut out, b"PLTE", &plte);
+        if !plan.trns.is_empty() {
+            push_chunk(&mut out, b"tRNS", &plan.trns);
+        }
+    }
+
+    push_chunk(&mut out, b"IDAT", &idat);
+    push_chunk(&mut out, b"IEND", &[]);
+
+    (out, pixo_color_type(plan.color_type), plan.bit_depth)
+}
+
 const MIN_TARGET_QUALITY: u8 = 1;
 
 fn estimate_quality(max_quality: u8, current_bytes: usize, target_bytes: usize) -> u8 {
@@ -233,9 +663,129 @@ fn encode_png_with_quality(
         clamp_u16(colors, 1, 256),
         dithering,
         force_quant,
+        preset,
     )
 }
 
+/// Extract the luminance channel (Rec. 601) from an RGBA buffer.
+fn to_luma(rgba: &[u8], width: u32, height: u32) -> Vec<f32> {
+    let px = (width as usize).saturating_mul(height as usize);
+    let mut luma = Vec::with_capacity(px);
+    for i in 0..px {
+        let r = rgba[i * 4] as f32;
+        let g = rgba[i * 4 + 1] as f32;
+        let b = rgba[i * 4 + 2] as f32;
+        luma.push(0.299 * r + 0.587 * g + 0.114 * b);
+    }
+    luma
+}
+
+/// Mean structural similarity between two equally sized luminance planes, using
+/// non-overlapping 8×8 windows. Returns MSSIM in `[0, 1]` (1 == identical).
+fn compute_mssim(a: &[f32], b: &[f32], width: u32, height: u32) -> f32 {
+    const WIN: usize = 8;
+    const C1: f32 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f32 = (0.03 * 255.0) * (0.03 * 255.0);
+    let (w, h) = (width as usize, height as usize);
+    if w < WIN || h < WIN || a.len() < w * h || b.len() < w * h {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+
+    let n = (WIN * WIN) as f32;
+    let mut sum = 0.0f32;
+    let mut windows = 0u32;
+    let mut wy = 0;
+    while wy + WIN <= h {
+        let mut wx = 0;
+        while wx + WIN <= w {
+            let (mut sx, mut sy, mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for dy in 0..WIN {
+                for dx in 0..WIN {
+                    let idx = (wy + dy) * w + (wx + dx);
+                    let x = a[idx];
+                    let y = b[idx];
+                    sx += x;
+                    sy += y;
+                    sxx += x * x;
+                    syy += y * y;
+                    sxy += x * y;
+                }
+            }
+            let mx = sx / n;
+            let my = sy / n;
+            let vx = sxx / n - mx * mx;
+            let vy = syy / n - my * my;
+            let cxy = sxy / n - mx * my;
+            let ssim = ((2.0 * mx * my + C1) * (2.0 * cxy + C2))
+                / ((mx * mx + my * my + C1) * (vx + vy + C2));
+            sum += ssim;
+            windows += 1;
+            wx += WIN;
+        }
+        wy += WIN;
+    }
+
+    if windows == 0 {
+        1.0
+    } else {
+        (sum / windows as f32).clamp(0.0, 1.0)
+    }
+}
+
+/// MSSIM of an encoded image (JPEG/PNG) against a reference luminance plane, or
+/// `0.0` if the trial bytes cannot be decoded back to pixels.
+fn mssim_of_encoded(encoded: &[u8], reference: &[f32], width: u32, height: u32) -> f32 {
+    match image::load_from_memory(encoded) {
+        Ok(img) => {
+            let rgba = img.to_rgba8();
+            if rgba.dimensions() != (width, height) {
+                return 0.0;
+            }
+            compute_mssim(&to_luma(rgba.as_raw(), width, height), reference, width, height)
+        }
+        Err(_) => 0.0,
+    }
+}
+
+/// Smallest file whose re-decoded image meets the MSSIM floor.
+///
+/// `encode` maps a quality to encoded bytes. SSIM rises monotonically with
+/// quality, so we binary-search for the lowest quality that clears `min_ssim`
+/// against `reference` (the post-resize source luminance) — that quality yields
+/// the smallest acceptable file. Callers apply any hard byte cap afterwards.
+fn search_for_ssim<F>(
+    max_quality: u8,
+    min_ssim: f32,
+    reference: &[f32],
+    width: u32,
+    height: u32,
+    mut encode: F,
+) -> Vec<u8>
+where
+    F: FnMut(u8) -> Vec<u8>,
+{
+    let mut lo = MIN_TARGET_QUALITY.min(max_quality);
+    let mut hi = max_quality;
+    let mut floor_out: Option<Vec<u8>> = None;
+
+    while lo <= hi {
+        let mid = ((lo as u16 + hi as u16) / 2) as u8;
+        let out = encode(mid);
+        if mssim_of_encoded(&out, reference, width, height) >= min_ssim {
+            floor_out = Some(out);
+            if mid == lo {
+                break;
+            }
+            hi = mid - 1;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    // Nothing cleared the floor: fall back to the highest-quality (best) encode.
+    floor_out.unwrap_or_else(|| encode(max_quality))
+}
+
 fn compress_png_to_target(
     rgba: &[u8],
     width: u32,
@@ -383,11 +933,12 @@ fn compress_pdf_to_target(
     pdf: Vec<u8>,
     max_quality: u8,
     preset: u8,
+    max_side: u32,
     target_bytes: usize,
 ) -> Vec<u8> {
     let max_quality = clamp_u8(max_quality, 1, 100);
     let min_quality = MIN_TARGET_QUALITY.min(max_quality);
-    let best = compress_pdf_images(pdf.clone(), max_quality, preset);
+    let best = compress_pdf_images(pdf.clone(), max_quality, preset, max_side);
     if best.len() <= target_bytes {
         return best;
     }
@@ -406,7 +957,7 @@ fn compress_pdf_to_target(
     while lo <= hi && iterations < 6 {
         let mid = (lo as u16 + hi as u16) / 2;
         let mid_q = mid as u8;
-        let out = compress_pdf_images(pdf.clone(), mid_q, preset);
+        let out = compress_pdf_images(pdf.clone(), mid_q, preset, max_side);
         if out.len() < smallest.len() {
             smallest = out.clone();
         }
@@ -425,6 +976,269 @@ fn compress_pdf_to_target(
     best_under.unwrap_or(smallest)
 }
 
+/// Decode a (possibly multi-strip) TIFF into a single 8-bit RGBA buffer.
+///
+/// The primary image is read via the `tiff` crate, which reassembles the strips
+/// internally; any additional pages are ignored so the result feeds the same
+/// single-image resize + target-byte pipeline as JPEG/PNG input.
+fn decode_tiff_rgba(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    let mut decoder = Decoder::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let (width, height) = decoder.dimensions().map_err(|e| e.to_string())?;
+    let colortype = decoder.colortype().map_err(|e| e.to_string())?;
+    let image = decoder.read_image().map_err(|e| e.to_string())?;
+
+    let samples = match image {
+        DecodingResult::U8(data) => data,
+        DecodingResult::U16(data) => data.into_iter().map(|v| (v >> 8) as u8).collect(),
+        _ => return Err("unsupported TIFF sample format".to_string()),
+    };
+
+    let px = (width as usize).saturating_mul(height as usize);
+    let mut rgba = Vec::with_capacity(px * 4);
+    match colortype {
+        colortype::ColorType::RGBA(_) => {
+            if samples.len() < px * 4 {
+                return Err("truncated TIFF image data".to_string());
+            }
+            rgba.extend_from_slice(&samples[..px * 4]);
+        }
+        colortype::ColorType::RGB(_) => {
+            if samples.len() < px * 3 {
+                return Err("truncated TIFF image data".to_string());
+            }
+            for i in 0..px {
+                rgba.push(samples[i * 3]);
+                rgba.push(samples[i * 3 + 1]);
+                rgba.push(samples[i * 3 + 2]);
+                rgba.push(255);
+            }
+        }
+        colortype::ColorType::Gray(_) => {
+            if samples.len() < px {
+                return Err("truncated TIFF image data".to_string());
+            }
+            for &v in &samples[..px] {
+                rgba.push(v);
+                rgba.push(v);
+                rgba.push(v);
+                rgba.push(255);
+            }
+        }
+        colortype::ColorType::GrayA(_) => {
+            if samples.len() < px * 2 {
+                return Err("truncated TIFF image data".to_string());
+            }
+            for i in 0..px {
+                let v = samples[i * 2];
+                rgba.push(v);
+                rgba.push(v);
+                rgba.push(v);
+                rgba.push(samples[i * 2 + 1]);
+            }
+        }
+        _ => return Err("unsupported TIFF color type".to_string()),
+    }
+
+    Ok((width, height, rgba))
+}
+
+/// Re-encode an RGBA buffer as a TIFF using the requested compression scheme.
+/// `compression` selects between `deflate`, `lzw`, and `packbits`; anything else
+/// (including `none`) emits an uncompressed TIFF.
+fn encode_tiff_rgba(rgba: &[u8], width: u32, height: u32, compression: &str) -> Vec<u8> {
+    let px = (width as usize).saturating_mul(height as usize);
+    if px == 0 || rgba.len() < px * 4 {
+        return Vec::new();
+    }
+
+    let mut buf = Cursor::new(Vec::new());
+    let ok = {
+        let mut encoder = match TiffEncoder::new(&mut buf) {
+            Ok(encoder) => encoder,
+            Err(_) => return Vec::new(),
+        };
+        let data = &rgba[..px * 4];
+        match compression {
+            "deflate" => encoder.write_image_with_compression::<colortype::RGBA8, _>(
+                width, height, Deflate::default(), data,
+            ),
+            "lzw" => encoder.write_image_with_compression::<colortype::RGBA8, _>(
+                width, height, Lzw, data,
+            ),
+            "packbits" => encoder.write_image_with_compression::<colortype::RGBA8, _>(
+                width, height, Packbits, data,
+            ),
+            _ => encoder.write_image_with_compression::<colortype::RGBA8, _>(
+                width, height, Uncompressed, data,
+            ),
+        }
+        .is_ok()
+    };
+
+    if ok {
+        buf.into_inner()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Read the EXIF orientation tag (1..=8) from the source bytes, defaulting to 1
+/// (no transform) when absent or unreadable.
+fn extract_orientation(bytes: &[u8]) -> u8 {
+    let mut cursor = Cursor::new(bytes);
+    let reader = exif::Reader::new();
+    let exif = match reader.read_from_container(&mut cursor) {
+        Ok(exif) => exif,
+        Err(_) => return 1,
+    };
+    match exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY) {
+        Some(field) => match field.value.get_uint(0) {
+            Some(v @ 1..=8) => v as u8,
+            _ => 1,
+        },
+        None => 1,
+    }
+}
+
+/// Apply the transform that an EXIF orientation value encodes, returning an
+/// upright buffer. The eight cases cover the rotations and their mirrored
+/// variants; dimensions swap for the 90°/270° cases.
+fn apply_orientation(img: image::RgbaImage, orientation: u8) -> image::RgbaImage {
+    use image::imageops::{flip_horizontal, flip_vertical, rotate180, rotate270, rotate90};
+    match orientation {
+        2 => flip_horizontal(&img),
+        3 => rotate180(&img),
+        4 => flip_vertical(&img),
+        5 => rotate270(&flip_horizontal(&img)),
+        6 => rotate90(&img),
+        7 => rotate90(&flip_horizontal(&img)),
+        8 => rotate270(&img),
+        _ => img,
+    }
+}
+
+/// Extract the embedded ICC profile from a JPEG (concatenated APP2
+/// `ICC_PROFILE` segments, reassembled in sequence order) or a PNG (the
+/// zlib-compressed `iCCP` chunk). Returns `None` when no profile is present.
+fn extract_icc(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() >= 2 && bytes[0] == 0xff && bytes[1] == 0xd8 {
+        return extract_icc_jpeg(bytes);
+    }
+    if bytes.len() >= 8 && bytes[0..8] == PNG_SIGNATURE {
+        return extract_icc_png(bytes);
+    }
+    None
+}
+
+fn extract_icc_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    const TAG: &[u8; 12] = b"ICC_PROFILE\0";
+    let mut pos = 2;
+    // Collect (sequence number, payload) so out-of-order markers reassemble right.
+    let mut chunks: Vec<(u8, Vec<u8>)> = Vec::new();
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xff {
+            break;
+        }
+        let marker = bytes[pos + 1];
+        if marker == 0xd9 || marker == 0xda {
+            break; // EOI / start of scan
+        }
+        let len = ((bytes[pos + 2] as usize) << 8) | bytes[pos + 3] as usize;
+        if len < 2 || pos + 2 + len > bytes.len() {
+            break;
+        }
+        let seg = &bytes[pos + 4..pos + 2 + len];
+        if marker == 0xe2 && seg.len() > 14 && &seg[0..12] == TAG {
+            chunks.push((seg[12], seg[14..].to_vec()));
+        }
+        pos += 2 + len;
+    }
+    if chunks.is_empty() {
+        return None;
+    }
+    chunks.sort_by_key(|(seq, _)| *seq);
+    Some(chunks.into_iter().flat_map(|(_, data)| data).collect())
+}
+
+fn extract_icc_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 8;
+    while pos + 8 <= bytes.len() {
+        let len = u32::from_be_bytes(bytes[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        let data_start = pos + 8;
+        if data_start + len > bytes.len() {
+            return None;
+        }
+        if kind == b"iCCP" {
+            let data = &bytes[data_start..data_start + len];
+            // profile name, NUL, compression method (0 = zlib), compressed profile
+            let nul = data.iter().position(|&b| b == 0)?;
+            let compressed = data.get(nul + 2..)?;
+            let mut decoder = ZlibDecoder::new(compressed);
+            let mut out = Vec::new();
+            return decoder.read_to_end(&mut out).ok().map(|_| out);
+        }
+        if kind == b"IDAT" || kind == b"IEND" {
+            return None;
+        }
+        pos = data_start + len + 4; // skip data + CRC
+    }
+    None
+}
+
+/// Re-embed an ICC profile into a JPEG as one or more APP2 `ICC_PROFILE`
+/// segments, inserted immediately after SOI and split across markers when the
+/// profile exceeds the 65535-byte segment limit.
+fn embed_icc_jpeg(jpeg: Vec<u8>, icc: &[u8]) -> Vec<u8> {
+    if jpeg.len() < 2 || jpeg[0] != 0xff || jpeg[1] != 0xd8 || icc.is_empty() {
+        return jpeg;
+    }
+    // Payload per marker: 65535 - 2 (length) - 12 (tag) - 2 (seq/total).
+    const MAX_PAYLOAD: usize = 65535 - 2 - 12 - 2;
+    let total = icc.len().div_ceil(MAX_PAYLOAD).min(255) as u8;
+
+    let mut out = Vec::with_capacity(jpeg.len() + icc.len() + 32 * total as usize);
+    out.extend_from_slice(&jpeg[0..2]); // SOI
+    for (i, chunk) in icc.chunks(MAX_PAYLOAD).enumerate().take(255) {
+        let seg_len = (2 + 12 + 2 + chunk.len()) as u16;
+        out.extend_from_slice(&[0xff, 0xe2]);
+        out.extend_from_slice(&seg_len.to_be_bytes());
+        out.extend_from_slice(b"ICC_PROFILE\0");
+        out.push((i + 1) as u8);
+        out.push(total);
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&jpeg[2..]);
+    out
+}
+
+/// Re-embed an ICC profile into a PNG as an `iCCP` chunk placed right after
+/// IHDR, with the profile zlib-compressed per the PNG spec.
+fn embed_icc_png(png: Vec<u8>, icc: &[u8]) -> Vec<u8> {
+    if png.len() < 8 || png[0..8] != PNG_SIGNATURE || icc.is_empty() {
+        return png;
+    }
+    // IHDR always immediately follows the signature: 8 + 4 len + 4 tag + 13 + 4 crc.
+    let ihdr_end = 8 + 12 + 13;
+    if png.len() < ihdr_end {
+        return png;
+    }
+    let mut data = Vec::new();
+    data.extend_from_slice(b"ICC Profile");
+    data.push(0); // name terminator
+    data.push(0); // compression method: zlib
+    data.extend_from_slice(&deflate(icc));
+
+    let mut chunk = Vec::new();
+    push_chunk(&mut chunk, b"iCCP", &data);
+
+    let mut out = Vec::with_capacity(png.len() + chunk.len());
+    out.extend_from_slice(&png[..ihdr_end]);
+    out.extend_from_slice(&chunk);
+    out.extend_from_slice(&png[ihdr_end..]);
+    out
+}
+
 #[wasm_bindgen]
 pub fn compress_file(
     bytes: Vec<u8>,
@@ -443,6 +1257,9 @@ pub fn compress_file(
     png_dither: bool,
     png_force_quant: bool,
     bg_transparent: bool,
+    tiff_compression: String,
+    min_ssim: f32,
+    strip_metadata: bool,
 ) -> Result<CompressionResult, JsValue> {
     if bytes.is_empty() {
         return Ok(CompressionResult::new(Vec::new(), "jpeg".to_string()));
@@ -454,6 +1271,7 @@ pub fn compress_file(
     let kind = input_kind(&mime, &ext);
     let orig_len = orig_bytes.len();
     let is_pdf_input = is_pdf(&mime, &ext, &orig_bytes);
+    let is_tiff_input = !is_pdf_input && is_tiff(&mime, &ext, &orig_bytes);
     let mut out_mode = choose_out_mode(&mime, &ext, &out_mode_sel);
     if is_pdf_input {
         out_mode = "pdf".to_string();
@@ -470,16 +1288,37 @@ pub fn compress_file(
 
     if out_mode == "pdf" {
         let out = if target_bytes > 0 {
-            compress_pdf_to_target(orig_bytes, quality, preset, target_bytes)
+            compress_pdf_to_target(orig_bytes, quality, preset, max_side, target_bytes)
         } else {
-            compress_pdf_images(orig_bytes, quality, preset)
+            compress_pdf_images(orig_bytes, quality, preset, max_side)
         };
         return Ok(CompressionResult::new(out, "pdf".to_string()));
     }
 
-    let img = image::load_from_memory(&orig_bytes)
-        .map_err(|e| JsValue::from_str(&format!("Decode failed: {e}")))?;
-    let mut rgba = img.to_rgba8();
+    // Pull the ICC profile and EXIF orientation off the source before decode;
+    // `image` drops both. `strip_metadata` opts out of carrying the profile.
+    let icc_profile = if strip_metadata {
+        None
+    } else {
+        extract_icc(&orig_bytes)
+    };
+    let orientation = extract_orientation(&orig_bytes);
+
+    let mut rgba = if is_tiff_input {
+        let (tw, th, data) = decode_tiff_rgba(&orig_bytes)
+            .map_err(|e| JsValue::from_str(&format!("Decode failed: {e}")))?;
+        image::RgbaImage::from_raw(tw, th, data)
+            .ok_or_else(|| JsValue::from_str("Decode failed: bad TIFF dimensions"))?
+    } else {
+        let img = image::load_from_memory(&orig_bytes)
+            .map_err(|e| JsValue::from_str(&format!("Decode failed: {e}")))?;
+        img.to_rgba8()
+    };
+    // Bake the orientation into the pixels before any resize so downstream
+    // stages work on an upright buffer.
+    if orientation != 1 {
+        rgba = apply_orientation(rgba, orientation);
+    }
     let (w, h) = rgba.dimensions();
     let (new_w, new_h) = scale_to_max_side(w, h, max_side);
     let resized = new_w != w || new_h != h;
@@ -491,7 +1330,40 @@ pub fn compress_file(
 
     if out_mode == "png" {
         let png_mode = png_mode.to_lowercase();
-        let enc_bytes = if target_bytes > 0 {
+        let enc_bytes = if min_ssim > 0.0 {
+            let reference = to_luma(&rgba_raw, width, height);
+            let out = search_for_ssim(quality, min_ssim, &reference, width, height, |q| {
+                encode_png_with_quality(
+                    &rgba_raw,
+                    width,
+                    height,
+                    q,
+                    preset,
+                    &png_mode,
+                    png_max_colors,
+                    png_dither,
+                    png_force_quant,
+                )
+            });
+            // A byte cap is a hard limit: if the floor-meeting file overshoots it,
+            // fall back to the pure byte-size search even below the SSIM floor.
+            if target_bytes > 0 && out.len() > target_bytes {
+                compress_png_to_target(
+                    &rgba_raw,
+                    width,
+                    height,
+                    preset,
+                    &png_mode,
+                    png_max_colors,
+                    png_dither,
+                    png_force_quant,
+                    quality,
+                    target_bytes,
+                )
+            } else {
+                out
+            }
+        } else if target_bytes > 0 {
             compress_png_to_target(
                 &rgba_raw,
                 width,
@@ -517,13 +1389,42 @@ pub fn compress_file(
                 png_force_quant,
             )
         };
-        if target_bytes == 0 && !resized && kind == "png" && enc_bytes.len() >= orig_len {
+        if target_bytes == 0 && !resized && orientation == 1 && kind == "png"
+            && enc_bytes.len() >= orig_len
+        {
             return Ok(CompressionResult::new(orig_bytes, "png".to_string()));
         }
+        let enc_bytes = match &icc_profile {
+            Some(icc) => embed_icc_png(enc_bytes, icc),
+            None => enc_bytes,
+        };
         return Ok(CompressionResult::new(enc_bytes, "png".to_string()));
     }
 
-    let enc_bytes = if target_bytes > 0 {
+    if out_mode == "tiff" {
+        let scheme = tiff_compression.to_lowercase();
+        let enc_bytes = encode_tiff_rgba(&rgba_raw, width, height, &scheme);
+        if target_bytes == 0 && !resized && orientation == 1 && kind == "tiff"
+            && enc_bytes.len() >= orig_len
+        {
+            return Ok(CompressionResult::new(orig_bytes, "tiff".to_string()));
+        }
+        return Ok(CompressionResult::new(enc_bytes, "tiff".to_string()));
+    }
+
+    let enc_bytes = if min_ssim > 0.0 {
+        let reference = to_luma(&rgba_raw, width, height);
+        let out = search_for_ssim(quality, min_ssim, &reference, width, height, |q| {
+            encode_jpeg_rgba(width, height, rgba_raw.clone(), q, preset, bg_r, bg_g, bg_b)
+        });
+        if target_bytes > 0 && out.len() > target_bytes {
+            compress_jpeg_to_target(
+                &rgba_raw, width, height, preset, bg_r, bg_g, bg_b, quality, target_bytes,
+            )
+        } else {
+            out
+        }
+    } else if target_bytes > 0 {
         compress_jpeg_to_target(
             &rgba_raw,
             width,
@@ -538,14 +1439,69 @@ pub fn compress_file(
     } else {
         encode_jpeg_rgba(width, height, rgba_raw, quality, preset, bg_r, bg_g, bg_b)
     };
-    if target_bytes == 0 && !resized && kind == "jpeg" && enc_bytes.len() >= orig_len {
+    if target_bytes == 0 && !resized && orientation == 1 && kind == "jpeg"
+        && enc_bytes.len() >= orig_len
+    {
         return Ok(CompressionResult::new(orig_bytes, "jpeg".to_string()));
     }
+    let enc_bytes = match &icc_profile {
+        Some(icc) => embed_icc_jpeg(enc_bytes, icc),
+        None => enc_bytes,
+    };
     Ok(CompressionResult::new(enc_bytes, "jpeg".to_string()))
 }
 
+/// Lanczos-downscale a packed RGB buffer so its longest side is at most
+/// `max_side`, returning the resized buffer and its new dimensions. A no-op (and
+/// the buffer moved back out) when the image already fits or `max_side` is 0.
+fn downscale_rgb(rgb: Vec<u8>, width: usize, height: usize, max_side: u32) -> (Vec<u8>, usize, usize) {
+    let (new_w, new_h) = scale_to_max_side(width as u32, height as u32, max_side);
+    if new_w as usize == width && new_h as usize == height {
+        return (rgb, width, height);
+    }
+    let buffer = match image::RgbImage::from_raw(width as u32, height as u32, rgb) {
+        Some(buffer) => buffer,
+        None => return (Vec::new(), width, height),
+    };
+    let resized = image::imageops::resize(&buffer, new_w, new_h, FilterType::Lanczos3);
+    (resized.into_raw(), new_w as usize, new_h as usize)
+}
+
+/// Expand an inline `[/Indexed base hival lookup]` color space to packed RGB.
+/// Only inline palettes with a `DeviceRGB`/`DeviceGray` base and a literal string
+/// lookup table are handled; references (which we cannot resolve while `objects`
+/// is mutably borrowed) make the caller skip the stream.
+fn expand_indexed(indices: &[u8], cs: &[Object]) -> Option<Vec<u8>> {
+    if cs.len() != 4 || cs[0].as_name().ok() != Some(b"Indexed") {
+        return None;
+    }
+    let base = cs[1].as_name().ok()?;
+    let components = match base {
+        b"DeviceRGB" => 3usize,
+        b"DeviceGray" => 1usize,
+        _ => return None,
+    };
+    let lookup = cs[3].as_str().ok()?;
+    let mut out = Vec::with_capacity(indices.len() * 3);
+    for &idx in indices {
+        let base_off = idx as usize * components;
+        if base_off + components > lookup.len() {
+            return None;
+        }
+        if components == 3 {
+            out.extend_from_slice(&lookup[base_off..base_off + 3]);
+        } else {
+            let v = lookup[base_off];
+            out.push(v);
+            out.push(v);
+            out.push(v);
+        }
+    }
+    Some(out)
+}
+
 #[wasm_bindgen]
-pub fn compress_pdf_images(pdf: Vec<u8>, quality: u8, preset: u8) -> Vec<u8> {
+pub fn compress_pdf_images(pdf: Vec<u8>, quality: u8, preset: u8, max_side: u32) -> Vec<u8> {
     if pdf.is_empty() {
         return Vec::new();
     }
@@ -574,67 +1530,90 @@ pub fn compress_pdf_images(pdf: Vec<u8>, quality: u8, preset: u8) -> Vec<u8> {
             Ok(filters) => filters,
             Err(_) => continue,
         };
-        if filters.len() != 1 || filters[0] != b"FlateDecode" {
+        if filters.len() != 1 {
+            continue;
+        }
+        let is_dct = filters[0] == b"DCTDecode";
+        if filters[0] != b"FlateDecode" && !is_dct {
             continue;
         }
 
-        let width = match stream.dict.get(b"Width").and_then(Object::as_i64) {
+        let mut width = match stream.dict.get(b"Width").and_then(Object::as_i64) {
             Ok(value) if value > 0 => value as usize,
             _ => continue,
         };
-        let height = match stream.dict.get(b"Height").and_then(Object::as_i64) {
+        let mut height = match stream.dict.get(b"Height").and_then(Object::as_i64) {
             Ok(value) if value > 0 => value as usize,
             _ => continue,
         };
-        let bits = match stream
-            .dict
-            .get(b"BitsPerComponent")
-            .and_then(Object::as_i64)
-        {
-            Ok(value) => value,
-            _ => 8,
-        };
-        if bits != 8 {
-            continue;
-        }
-
-        let color_space = match stream.dict.get(b"ColorSpace") {
-            Ok(Object::Name(name)) if name == b"DeviceRGB" => "rgb",
-            Ok(Object::Name(name)) if name == b"DeviceGray" => "gray",
-            _ => continue,
-        };
-
-        let decoded = match stream.decompressed_content() {
-            Ok(data) => data,
-            Err(_) => continue,
-        };
-
         let px = match width.checked_mul(height) {
             Some(px) if px > 0 => px,
             _ => continue,
         };
 
-        let rgb = if color_space == "rgb" {
-            let expected = match px.checked_mul(3) {
-                Some(value) => value,
-                None => continue,
-            };
-            if decoded.len() != expected {
-                continue;
+        // Decode the stream to packed RGB, regardless of how it was stored.
+        let mut rgb = if is_dct {
+            // The raw stream content is a baseline JPEG; decode it through `image`.
+            match image::load_from_memory(&stream.content) {
+                Ok(img) => img.to_rgb8().into_raw(),
+                Err(_) => continue,
             }
-            decoded
         } else {
-            if decoded.len() != px {
+            let bits = match stream.dict.get(b"BitsPerComponent").and_then(Object::as_i64) {
+                Ok(value) => value,
+                _ => 8,
+            };
+            if bits != 8 {
                 continue;
             }
-            let mut out = Vec::with_capacity(px * 3);
-            for v in decoded {
-                out.push(v);
-                out.push(v);
-                out.push(v);
+            let decoded = match stream.decompressed_content() {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+            match stream.dict.get(b"ColorSpace") {
+                Ok(Object::Name(name)) if name == b"DeviceRGB" => {
+                    if decoded.len() != px * 3 {
+                        continue;
+                    }
+                    decoded
+                }
+                Ok(Object::Name(name)) if name == b"DeviceGray" => {
+                    if decoded.len() != px {
+                        continue;
+                    }
+                    let mut out = Vec::with_capacity(px * 3);
+                    for v in decoded {
+                        out.push(v);
+                        out.push(v);
+                        out.push(v);
+                    }
+                    out
+                }
+                Ok(Object::Array(cs)) => {
+                    if decoded.len() != px {
+                        continue;
+                    }
+                    match expand_indexed(&decoded, cs) {
+                        Some(rgb) => rgb,
+                        None => continue,
+                    }
+                }
+                _ => continue,
             }
-            out
         };
+        if rgb.len() != px * 3 {
+            continue;
+        }
+
+        // Lanczos-downscale oversized scans before re-encoding.
+        let (resized, new_w, new_h) = downscale_rgb(rgb, width, height, max_side);
+        if resized.is_empty() {
+            continue;
+        }
+        let downscaled = new_w != width || new_h != height;
+        rgb = resized;
+        width = new_w;
+        height = new_h;
 
         let mut opts = JpegOptions::from_preset(width as u32, height as u32, quality, preset);
         opts.color_type = ColorType::Rgb;
@@ -642,6 +1621,11 @@ pub fn compress_pdf_images(pdf: Vec<u8>, quality: u8, preset: u8) -> Vec<u8> {
         if jpeg_bytes.is_empty() {
             continue;
         }
+        // For already-DCTDecode streams, only rewrite if we actually shrank it and
+        // did not simply grow the file at the same dimensions.
+        if is_dct && !downscaled && jpeg_bytes.len() >= stream.content.len() {
+            continue;
+        }
 
         stream.set_content(jpeg_bytes);
         stream
@@ -650,6 +1634,8 @@ pub fn compress_pdf_images(pdf: Vec<u8>, quality: u8, preset: u8) -> Vec<u8> {
         stream
             .dict
             .set("ColorSpace", Object::Name(b"DeviceRGB".to_vec()));
+        stream.dict.set("Width", width as i64);
+        stream.dict.set("Height", height as i64);
         stream.dict.set("BitsPerComponent", 8);
         stream.dict.remove(b"DecodeParms");
         changed = true;